@@ -0,0 +1,204 @@
+use crate::block::{BlockDate, Header};
+use crate::ledger::Ledger;
+
+/// How a candidate chain compares against the one currently held. The naming
+/// mirrors [`std::cmp::Ordering`] but is kept separate because "better" is not
+/// a total order derived from the block dates alone: it folds in the length
+/// and density rules of the Ouroboros-Genesis *maxvalid-bg* selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainComparison {
+    /// the candidate chain should be adopted over the reference chain
+    Better,
+    /// the candidate chain should be rejected in favour of the reference chain
+    Worse,
+    /// the two chains are indistinguishable under the rule
+    Equal,
+}
+
+/// Density based fork choice, parameterised by the security parameter `k` and
+/// the density window `s` taken from [`Ledger`] settings.
+///
+/// Once leadership is probabilistic a chain can legitimately contain empty
+/// slots, so the "longest chain" no longer uniquely identifies the honest
+/// history. *maxvalid-bg* guards against long-range and eclipse attacks by
+/// only falling back to a longest-chain comparison while the two chains have
+/// not diverged by more than `k` blocks; past that point it compares the
+/// block density in the bounded window immediately following the fork.
+#[derive(Debug, Clone, Copy)]
+pub struct ForkChoice {
+    security_parameter: u32,
+    density_window: u32,
+}
+
+impl ForkChoice {
+    /// Build the fork choice rule from the current ledger settings.
+    pub fn new(ledger: &Ledger) -> Self {
+        ForkChoice {
+            security_parameter: ledger.settings.security_parameter,
+            density_window: ledger.settings.density_window,
+        }
+    }
+
+    /// Compare the `candidate` chain against the `reference` chain. Both are
+    /// given from the most-recent common ancestor towards the tip (the
+    /// ancestor itself excluded); an empty slice denotes a chain that stops at
+    /// the common ancestor. `ancestor` is the block date of that common
+    /// ancestor itself, as found by the caller's chain-walk — it anchors the
+    /// density window and must not be inferred from either slice, since empty
+    /// slots mean the ancestor can sit arbitrarily far before the first
+    /// forked block.
+    pub fn compare(&self, reference: &[Header], candidate: &[Header], ancestor: BlockDate) -> ChainComparison {
+        let reference_slots: Vec<u64> = reference.iter().map(|h| slot_of(*h.block_date())).collect();
+        let candidate_slots: Vec<u64> = candidate.iter().map(|h| slot_of(*h.block_date())).collect();
+        let reference_tip_hash = reference.last().map(|h| h.hash());
+        let candidate_tip_hash = candidate.last().map(|h| h.hash());
+
+        self.compare_slots(
+            &reference_slots,
+            &candidate_slots,
+            reference_tip_hash.as_ref().map(|h| h.as_ref()),
+            candidate_tip_hash.as_ref().map(|h| h.as_ref()),
+            slot_of(ancestor),
+        )
+    }
+
+    /// the pure ordering logic behind [`ForkChoice::compare`], taken out to
+    /// slot numbers and tip hash bytes so it can be exercised directly
+    /// without building real [`Header`]s.
+    fn compare_slots(
+        &self,
+        reference_slots: &[u64],
+        candidate_slots: &[u64],
+        reference_tip_hash: Option<&[u8]>,
+        candidate_tip_hash: Option<&[u8]>,
+        s0: u64,
+    ) -> ChainComparison {
+        let reference_len = reference_slots.len();
+        let candidate_len = candidate_slots.len();
+
+        let behind = abs_diff(reference_len, candidate_len);
+        if behind <= self.security_parameter as usize {
+            // the shorter chain is at most `k` blocks behind the longer one:
+            // prefer strictly more blocks.
+            return match candidate_len.cmp(&reference_len) {
+                std::cmp::Ordering::Greater => ChainComparison::Better,
+                std::cmp::Ordering::Less => ChainComparison::Worse,
+                std::cmp::Ordering::Equal => self.break_tie(reference_tip_hash, candidate_tip_hash),
+            };
+        }
+
+        // deep fork: compare the density in the bounded window (s0, s0 + s].
+        let reference_density = self.density(reference_slots, s0);
+        let candidate_density = self.density(candidate_slots, s0);
+
+        match candidate_density.cmp(&reference_density) {
+            std::cmp::Ordering::Greater => ChainComparison::Better,
+            std::cmp::Ordering::Less => ChainComparison::Worse,
+            std::cmp::Ordering::Equal => self.break_tie(reference_tip_hash, candidate_tip_hash),
+        }
+    }
+
+    /// number of slots a chain has a block in, within the window `(s0, s0 + s]`.
+    fn density(&self, slots: &[u64], s0: u64) -> usize {
+        let upper = s0 + self.density_window as u64;
+        slots.iter().filter(|&&slot| slot > s0 && slot <= upper).count()
+    }
+
+    /// deterministic tie break: prefer the chain whose tip has the lowest
+    /// block hash so that every node converges on the same choice.
+    fn break_tie(&self, reference_tip_hash: Option<&[u8]>, candidate_tip_hash: Option<&[u8]>) -> ChainComparison {
+        match (reference_tip_hash, candidate_tip_hash) {
+            (Some(reference_tip), Some(candidate_tip)) => match candidate_tip.cmp(reference_tip) {
+                std::cmp::Ordering::Less => ChainComparison::Better,
+                std::cmp::Ordering::Greater => ChainComparison::Worse,
+                std::cmp::Ordering::Equal => ChainComparison::Equal,
+            },
+            _ => ChainComparison::Equal,
+        }
+    }
+}
+
+#[inline]
+fn slot_of(date: BlockDate) -> u64 {
+    date.slot_id as u64
+}
+
+#[inline]
+fn abs_diff(a: usize, b: usize) -> usize {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(k: u32, s: u32) -> ForkChoice {
+        ForkChoice { security_parameter: k, density_window: s }
+    }
+
+    #[test]
+    fn recent_fork_prefers_the_longer_chain() {
+        // within k=5 of the tip: pure longest-chain, density is irrelevant.
+        let rule = rule(5, 100);
+        let reference = vec![1, 2, 3];
+        let candidate = vec![1, 2, 3, 4];
+        assert_eq!(
+            rule.compare_slots(&reference, &candidate, None, None, 0),
+            ChainComparison::Better
+        );
+        assert_eq!(
+            rule.compare_slots(&candidate, &reference, None, None, 0),
+            ChainComparison::Worse
+        );
+    }
+
+    #[test]
+    fn deep_fork_prefers_the_denser_chain_even_if_shorter() {
+        // behind = 15 > k=2, so length alone no longer decides it: the
+        // shorter-but-denser chain (more blocks packed into the window right
+        // after the fork) must win over the longer-but-sparser one.
+        let rule = rule(2, 20);
+        let s0 = 100;
+        // reference: longer overall, but sparse inside the (100, 120] window
+        // (only 4 of its blocks land there; the rest are further out).
+        let reference: Vec<u64> = vec![
+            101, 107, 113, 119, 126, 132, 138, 144, 150, 156, 162, 168, 174, 180, 186, 192, 198,
+            204, 210, 216,
+        ];
+        // candidate: short, but every one of its blocks is inside the window.
+        let candidate: Vec<u64> = vec![101, 102, 103, 104, 105];
+
+        assert!(abs_diff(reference.len(), candidate.len()) > rule.security_parameter as usize);
+        assert_eq!(rule.density(&reference, s0), 4);
+        assert_eq!(rule.density(&candidate, s0), 5);
+        assert_eq!(
+            rule.compare_slots(&reference, &candidate, None, None, s0),
+            ChainComparison::Better
+        );
+    }
+
+    #[test]
+    fn equal_length_breaks_tie_on_lowest_tip_hash() {
+        let rule = rule(2, 20);
+        let s0 = 100;
+        let reference: Vec<u64> = (102..123).collect(); // identical shape both sides
+        let candidate: Vec<u64> = (102..123).collect();
+
+        assert_eq!(
+            rule.compare_slots(&reference, &candidate, Some(&[0xff]), Some(&[0x01]), s0),
+            ChainComparison::Better
+        );
+        assert_eq!(
+            rule.compare_slots(&reference, &candidate, Some(&[0x01]), Some(&[0xff]), s0),
+            ChainComparison::Worse
+        );
+        assert_eq!(
+            rule.compare_slots(&reference, &candidate, Some(&[0x01]), Some(&[0x01]), s0),
+            ChainComparison::Equal
+        );
+    }
+}
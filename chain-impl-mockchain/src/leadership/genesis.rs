@@ -0,0 +1,207 @@
+use crate::block::{BlockDate, Header, Proof};
+use crate::key::{deserialize_public_key, serialize_public_key};
+use crate::{
+    leadership::{Error, ErrorKind, Verification},
+    ledger::Ledger,
+    setting::ActiveSlotCoefficient,
+    stake::{PercentStake, StakeDistribution},
+};
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use chain_core::property;
+use chain_crypto::bech32::{Bech32, Error as Bech32Error};
+use chain_crypto::{
+    Curve25519_2HashDH, PublicKey, SecretKey, VerifiableRandomFunction, VrfVerification,
+};
+use std::sync::Arc;
+
+/// verifiable random function used to evaluate the genesis/praos slot
+/// leadership; the same primitive backs the per-slot eligibility lottery.
+#[allow(non_camel_case_types)]
+pub type VRF_ALGORITHM = Curve25519_2HashDH;
+
+/// secret half of a stakeholder's VRF keypair
+pub type VrfSigningKey = SecretKey<VRF_ALGORITHM>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GenesisPraosLeaderId(pub(crate) PublicKey<VRF_ALGORITHM>);
+
+/// The genesis/praos leadership elects, for every slot, the stakeholders
+/// whose VRF output over `epoch_nonce || slot_id` falls below the stake
+/// dependent threshold `φ(α)`. Unlike [`BftLeaderSelection`] a slot may end
+/// up with zero eligible leaders (an empty slot, no valid block) or with
+/// several of them (a fork, resolved by the density based fork choice).
+///
+/// [`BftLeaderSelection`]: super::bft::BftLeaderSelection
+#[derive(Debug)]
+pub struct GenesisLeaderSelection {
+    epoch_nonce: Nonce,
+    active_slot_coefficient: ActiveSlotCoefficient,
+    stake_distribution: Arc<StakeDistribution>,
+}
+
+/// per-epoch randomness, derived deterministically from the contributions of
+/// the blocks of the previous epoch so that every node agrees on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Nonce(pub(crate) [u8; 32]);
+
+impl GenesisLeaderSelection {
+    /// Create a new genesis/praos leadership out of the current ledger; returns
+    /// `None` when the ledger does not carry a stake distribution yet (for
+    /// instance before the first epoch transition).
+    pub fn new(ledger: &Ledger) -> Option<Self> {
+        let stake_distribution = ledger.settings.stake_distribution.clone()?;
+        Some(GenesisLeaderSelection {
+            epoch_nonce: ledger.settings.epoch_nonce,
+            active_slot_coefficient: ledger.settings.active_slot_coefficient,
+            stake_distribution,
+        })
+    }
+
+    /// seed fed to the VRF for a given slot: the per-epoch randomness followed
+    /// by the little-endian slot identifier.
+    fn seed(&self, date: BlockDate) -> [u8; 40] {
+        let mut seed = [0u8; 40];
+        seed[..32].copy_from_slice(&self.epoch_nonce.0);
+        seed[32..].copy_from_slice(&(date.slot_id as u64).to_le_bytes());
+        seed
+    }
+
+    /// `φ(α) = 1 - (1 - f)^α` where `f` is the active-slot coefficient and `α`
+    /// the fractional stake of the party. This is the probability that the
+    /// party is elected for any given slot.
+    ///
+    /// This is consensus-critical: every node must reach the same verdict for
+    /// the same block, so the result is a fixed-point value in
+    /// `[0, FIXED_SCALE)` obtained from the bounded Taylor (binomial) series
+    /// below rather than from `f64::powf`, whose transcendental approximation
+    /// is not guaranteed to agree bit-for-bit across platforms and libm
+    /// versions.
+    fn phi(&self, stake: PercentStake) -> i128 {
+        let f = to_fixed(self.active_slot_coefficient.as_f64());
+        let alpha = to_fixed(stake.as_f64());
+        FIXED_SCALE - one_minus_f_pow_alpha(f, alpha)
+    }
+
+    pub(crate) fn verify(&self, block_header: &Header) -> Verification {
+        match &block_header.proof() {
+            Proof::GenesisPraos(praos_proof) => {
+                let stake = match self.stake_distribution.for_leader(&praos_proof.leader_id) {
+                    Some(stake) => stake,
+                    None => return Verification::Failure(Error::new(ErrorKind::InvalidLeader)),
+                };
+
+                let seed = self.seed(*block_header.block_date());
+                let y = match VRF_ALGORITHM::verify(
+                    &praos_proof.leader_id.0,
+                    &seed,
+                    &praos_proof.vrf_proof,
+                ) {
+                    VrfVerification::Success(output) => interpret_as_fixed(&output),
+                    VrfVerification::Failed => {
+                        return Verification::Failure(Error::new(
+                            ErrorKind::InvalidLeaderSignature,
+                        ));
+                    }
+                };
+
+                if y < self.phi(stake) {
+                    Verification::Success
+                } else {
+                    Verification::Failure(Error::new(ErrorKind::InvalidLeader))
+                }
+            }
+            _ => Verification::Failure(Error::new(ErrorKind::InvalidLeaderSignature)),
+        }
+    }
+}
+
+/// fixed-point scale shared by every φ(α) computation: 10^18, giving ample
+/// precision while keeping every intermediate product well inside `i128`.
+const FIXED_SCALE: i128 = 1_000_000_000_000_000_000;
+
+/// index of the last term of the binomial series summed by
+/// [`one_minus_f_pow_alpha`] (terms `k = 0..=TAYLOR_TERMS`).
+/// Bounded so the computation terminates and is identical on every node;
+/// `f` is always in `(0, 1)` so the series converges well within this bound.
+const TAYLOR_TERMS: i128 = 24;
+
+/// convert an `f64` in `[0, 1]` to the `FIXED_SCALE` fixed-point
+/// representation. Only basic IEEE-754 arithmetic is used here (no
+/// transcendental functions), which is required to be correctly rounded and
+/// therefore portable across platforms.
+fn to_fixed(x: f64) -> i128 {
+    (x * FIXED_SCALE as f64).round() as i128
+}
+
+/// `(1 - f)^alpha`, computed in `FIXED_SCALE` fixed point via the bounded
+/// binomial (Taylor) series `Σ_{k=0}^{TAYLOR_TERMS} C(alpha, k) · (-f)^k`,
+/// using only integer `+`/`-`/`*`/`/` so the result is bit-identical on every
+/// node regardless of platform or libm version.
+fn one_minus_f_pow_alpha(f_fixed: i128, alpha_fixed: i128) -> i128 {
+    let mut term = FIXED_SCALE;
+    let mut sum = FIXED_SCALE;
+    let mut alpha_minus_k = alpha_fixed;
+    for k in 1..=TAYLOR_TERMS {
+        term = term * alpha_minus_k / FIXED_SCALE;
+        term = term * (-f_fixed) / FIXED_SCALE;
+        term /= k;
+        sum += term;
+        alpha_minus_k -= FIXED_SCALE;
+    }
+    sum
+}
+
+/// map the uniform VRF output onto a fixed-point fraction in
+/// `[0, FIXED_SCALE)`; the most significant bytes of the output are read as a
+/// big-endian fraction. Integer-only so the result is reproducible.
+fn interpret_as_fixed(output: &<VRF_ALGORITHM as VerifiableRandomFunction>::VerifiedOutput) -> i128 {
+    let bytes = output.as_ref();
+    let mut num = 0u64;
+    for byte in bytes.iter().take(8) {
+        num = (num << 8) | (*byte as u64);
+    }
+    ((num as u128 * FIXED_SCALE as u128) >> 64) as i128
+}
+
+impl GenesisPraosLeaderId {
+    pub fn as_public_key(&self) -> &PublicKey<VRF_ALGORITHM> {
+        &self.0
+    }
+}
+
+impl property::Serialize for GenesisPraosLeaderId {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
+        serialize_public_key(&self.0, writer)
+    }
+}
+
+impl Readable for GenesisPraosLeaderId {
+    fn read<'a>(reader: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        deserialize_public_key(reader).map(GenesisPraosLeaderId)
+    }
+}
+
+impl AsRef<[u8]> for GenesisPraosLeaderId {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl From<PublicKey<VRF_ALGORITHM>> for GenesisPraosLeaderId {
+    fn from(v: PublicKey<VRF_ALGORITHM>) -> Self {
+        GenesisPraosLeaderId(v)
+    }
+}
+
+impl Bech32 for GenesisPraosLeaderId {
+    const BECH32_HRP: &'static str = PublicKey::<VRF_ALGORITHM>::BECH32_HRP;
+
+    fn try_from_bech32_str(s: &str) -> Result<Self, Bech32Error> {
+        PublicKey::<VRF_ALGORITHM>::try_from_bech32_str(s).map(Self)
+    }
+
+    fn to_bech32_str(&self) -> String {
+        self.0.to_bech32_str()
+    }
+}
@@ -0,0 +1,87 @@
+//! Leadership: the rule that decides who may produce the block for a given
+//! slot, and how to arbitrate between chains once that rule is
+//! probabilistic.
+//!
+//! Two leadership modes are implemented: [`bft::BftLeaderSelection`], a
+//! deterministic round-robin over a fixed leader set, and
+//! [`genesis::GenesisLeaderSelection`], the Ouroboros-Praos VRF/stake
+//! lottery. [`LeaderSelection`] is the dispatching enum a [`Ledger`] actually
+//! holds; which variant is built is picked once from `Ledger.settings` and
+//! never mixed within a single chain. [`fork_choice::ForkChoice`] is the
+//! companion rule used to arbitrate between two valid chains once leadership
+//! is probabilistic.
+
+pub mod bft;
+pub mod fork_choice;
+pub mod genesis;
+
+use crate::block::Header;
+use crate::ledger::Ledger;
+use crate::setting::LeadershipMode;
+use bft::BftLeaderSelection;
+use genesis::GenesisLeaderSelection;
+
+/// outcome of validating a block header against the leadership rule in
+/// effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    Success,
+    Failure(Error),
+}
+
+/// a leadership verification failure, together with the reason it failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Self {
+        Error { kind }
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// the block was not produced by the leader the rule expected for its slot.
+    InvalidLeader,
+    /// the block's proof does not validate against the claimed leader.
+    InvalidLeaderSignature,
+}
+
+/// the leadership rule currently active for a ledger, selected from
+/// [`Ledger.settings.leadership_mode`]. Kept as an enum rather than a trait
+/// object since there are exactly two modes and callers can match on which
+/// one is in effect.
+#[derive(Debug)]
+pub enum LeaderSelection {
+    Bft(BftLeaderSelection),
+    Genesis(GenesisLeaderSelection),
+}
+
+impl LeaderSelection {
+    /// Build the leadership rule selected by `ledger.settings.leadership_mode`.
+    /// Returns `None` when the selected mode cannot be constructed from the
+    /// current ledger state (no BFT leaders configured, or no stake
+    /// distribution yet for genesis/praos).
+    pub fn new(ledger: &Ledger) -> Option<Self> {
+        match ledger.settings.leadership_mode {
+            LeadershipMode::Bft => BftLeaderSelection::new(ledger).map(LeaderSelection::Bft),
+            LeadershipMode::Genesis => {
+                GenesisLeaderSelection::new(ledger).map(LeaderSelection::Genesis)
+            }
+        }
+    }
+
+    /// Verify a block header against whichever leadership rule is active.
+    pub(crate) fn verify(&self, block_header: &Header) -> Verification {
+        match self {
+            LeaderSelection::Bft(bft) => bft.verify(block_header),
+            LeaderSelection::Genesis(genesis) => genesis.verify(block_header),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! BIP158-style compact block filters.
+//!
+//! For every block we build a Golomb-coded set (GCS) over the CBOR encoding of
+//! each output address. The set is keyed by the block hash so that membership
+//! queries never collide across blocks. A block's filter is built and stored
+//! by `filter_store::build_and_store`, never on the query path; the sync
+//! loop does not call it, so only blocks passed to it explicitly have a
+//! stored filter. `find-addresses` only ever reads the stored filter
+//! (`filter_store::load`) and skips a block outright when none of the
+//! watched addresses can match it, confirming exact matches with the full
+//! block only on a filter hit; for blocks that have a filter this replaces
+//! the scan of every output with a set-membership test. A block with no
+//! stored filter is scanned directly, exactly as `find-addresses` always did.
+
+use wallet_crypto::cbor;
+use wallet_crypto::address::ExtendedAddr;
+use siphasher::sip::SipHasher24;
+use std::hash::Hasher;
+
+/// false-positive parameter: the expected rate is `1 / M`.
+pub const M: u64 = 784931;
+/// Golomb-Rice parameter, `P = log2(M)` rounded down.
+pub const P: u8 = 19;
+
+/// A serialized Golomb-coded set for a single block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactFilter {
+    n: u64,
+    data: Vec<u8>,
+}
+
+/// writes individual bits, most-significant first, into a byte buffer.
+struct BitWriter {
+    out: Vec<u8>,
+    cur: u8,
+    nbits: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter { out: Vec::new(), cur: 0, nbits: 0 }
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        self.cur = (self.cur << 1) | (bit as u8);
+        self.nbits += 1;
+        if self.nbits == 8 {
+            self.out.push(self.cur);
+            self.cur = 0;
+            self.nbits = 0;
+        }
+    }
+
+    fn write_bits(&mut self, value: u64, len: u8) {
+        for i in (0..len).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.nbits > 0 {
+            self.cur <<= 8 - self.nbits;
+            self.out.push(self.cur);
+        }
+        self.out
+    }
+}
+
+/// reads individual bits, most-significant first, from a byte slice.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0 }
+    }
+
+    fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.pos / 8;
+        if byte >= self.data.len() {
+            return None;
+        }
+        let bit = 7 - (self.pos % 8);
+        self.pos += 1;
+        Some((self.data[byte] >> bit) & 1 == 1)
+    }
+
+    fn read_bits(&mut self, len: u8) -> Option<u64> {
+        let mut value = 0u64;
+        for _ in 0..len {
+            value = (value << 1) | (self.read_bit()? as u64);
+        }
+        Some(value)
+    }
+}
+
+/// map one element into `[0, N·M)` using SipHash keyed by the block hash.
+fn hash_to_range(block_hash: &[u8], element: &[u8], modulus: u64) -> u64 {
+    let (k0, k1) = siphash_key(block_hash);
+    let mut hasher = SipHasher24::new_with_keys(k0, k1);
+    hasher.write(element);
+    // 128-bit fixed point reduction of the 64-bit hash into [0, modulus).
+    ((hasher.finish() as u128 * modulus as u128) >> 64) as u64
+}
+
+/// derive the two SipHash keys from the first 16 bytes of the block hash.
+fn siphash_key(block_hash: &[u8]) -> (u64, u64) {
+    let mut k = [0u8; 16];
+    let len = block_hash.len().min(16);
+    k[..len].copy_from_slice(&block_hash[..len]);
+    let k0 = u64::from_le_bytes([k[0], k[1], k[2], k[3], k[4], k[5], k[6], k[7]]);
+    let k1 = u64::from_le_bytes([k[8], k[9], k[10], k[11], k[12], k[13], k[14], k[15]]);
+    (k0, k1)
+}
+
+impl CompactFilter {
+    /// Build the filter for a block out of its output addresses.
+    pub fn build(block_hash: &[u8], addresses: &[ExtendedAddr]) -> Self {
+        let elements: Vec<Vec<u8>> = addresses
+            .iter()
+            .map(|addr| cbor::encode_to_cbor(addr).unwrap())
+            .collect();
+        Self::build_from_elements(block_hash, &elements)
+    }
+
+    /// Build the Golomb-coded set out of raw elements. Split out from
+    /// [`CompactFilter::build`] so the set encoding itself can be exercised
+    /// without needing a real `ExtendedAddr`/CBOR round-trip.
+    fn build_from_elements(block_hash: &[u8], elements: &[Vec<u8>]) -> Self {
+        let n = elements.len() as u64;
+        let modulus = n.saturating_mul(M);
+
+        let mut values: Vec<u64> = elements
+            .iter()
+            .map(|element| hash_to_range(block_hash, element, modulus))
+            .collect();
+        values.sort_unstable();
+
+        let mut writer = BitWriter::new();
+        let mut last = 0u64;
+        for value in values {
+            let delta = value - last;
+            last = value;
+            // Golomb-Rice: unary quotient, then P-bit remainder.
+            let quotient = delta >> P;
+            for _ in 0..quotient {
+                writer.write_bit(true);
+            }
+            writer.write_bit(false);
+            writer.write_bits(delta & ((1 << P) - 1), P);
+        }
+
+        CompactFilter { n, data: writer.finish() }
+    }
+
+    /// Test whether `address` may be present in the block. False positives are
+    /// possible (at a rate of `1 / M`); false negatives are not.
+    pub fn contains(&self, block_hash: &[u8], address: &ExtendedAddr) -> bool {
+        let element = cbor::encode_to_cbor(address).unwrap();
+        self.contains_element(block_hash, &element)
+    }
+
+    /// Membership test against a raw element; see [`CompactFilter::build_from_elements`].
+    fn contains_element(&self, block_hash: &[u8], element: &[u8]) -> bool {
+        if self.n == 0 {
+            return false;
+        }
+        let modulus = self.n.saturating_mul(M);
+        let target = hash_to_range(block_hash, element, modulus);
+
+        let mut reader = BitReader::new(&self.data);
+        let mut value = 0u64;
+        for _ in 0..self.n {
+            let mut quotient = 0u64;
+            while let Some(true) = reader.read_bit() {
+                quotient += 1;
+            }
+            let remainder = match reader.read_bits(P) {
+                Some(r) => r,
+                None => return false,
+            };
+            value += (quotient << P) | remainder;
+            if value == target {
+                return true;
+            }
+            if value > target {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Serialize the set as `N || golomb-coded-deltas` for storage.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + self.data.len());
+        out.extend_from_slice(&self.n.to_le_bytes());
+        out.extend_from_slice(&self.data);
+        out
+    }
+
+    /// Reconstruct a filter from its stored bytes.
+    pub fn deserialize(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 8 {
+            return None;
+        }
+        let mut n = [0u8; 8];
+        n.copy_from_slice(&bytes[..8]);
+        Some(CompactFilter {
+            n: u64::from_le_bytes(n),
+            data: bytes[8..].to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn elements(strs: &[&str]) -> Vec<Vec<u8>> {
+        strs.iter().map(|s| s.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn round_trips_present_elements() {
+        let block_hash = b"some-block-hash-some-block-hash";
+        let present = elements(&["addr-a", "addr-b", "addr-c", "addr-d"]);
+        let filter = CompactFilter::build_from_elements(block_hash, &present);
+
+        for element in &present {
+            assert!(filter.contains_element(block_hash, element));
+        }
+    }
+
+    #[test]
+    fn serialize_round_trips() {
+        let block_hash = b"some-block-hash-some-block-hash";
+        let present = elements(&["addr-a", "addr-b", "addr-c"]);
+        let filter = CompactFilter::build_from_elements(block_hash, &present);
+
+        let restored = CompactFilter::deserialize(&filter.serialize()).unwrap();
+        for element in &present {
+            assert!(restored.contains_element(block_hash, element));
+        }
+    }
+
+    #[test]
+    fn known_absent_element_is_almost_always_rejected() {
+        let block_hash = b"some-block-hash-some-block-hash";
+        let present = elements(&["addr-a", "addr-b", "addr-c"]);
+        let filter = CompactFilter::build_from_elements(block_hash, &present);
+
+        // a P=19, M=784931 filter has a false-positive rate of ~1/M, so one
+        // absent element not matching is not itself a guarantee — but it
+        // would take extraordinary bad luck to hit the 1/M chance here.
+        assert!(!filter.contains_element(block_hash, b"addr-not-in-the-block"));
+    }
+
+    #[test]
+    fn empty_block_contains_nothing() {
+        let block_hash = b"some-block-hash-some-block-hash";
+        let filter = CompactFilter::build_from_elements(block_hash, &[]);
+        assert!(!filter.contains_element(block_hash, b"anything"));
+    }
+}
@@ -0,0 +1,61 @@
+//! Storage for the per-block compact filters, keyed by block hash.
+//!
+//! [`build_and_store`] builds the filter for one block and writes it next to
+//! the block, keyed by its hash; the sync loop does not call it, so a block
+//! only has a filter once `build_and_store` has been run for it. [`load`] is
+//! the query-side counterpart and is read-only: it returns the stored filter
+//! if one exists and `None` otherwise, and never builds, writes, or panics.
+//! A block with no stored filter simply has no filter to consult; callers
+//! fall back to scanning that block directly rather than paying to build one
+//! on the read path.
+
+use wallet_crypto::address::ExtendedAddr;
+use storage::{Storage, blob};
+use blockchain::{Block, HeaderHash};
+use super::compact_filter::CompactFilter;
+
+/// namespace prefix folded into the filter's key so it hashes to a different
+/// [`HeaderHash`] than the block's own blob, even though both are derived
+/// from the same block hash.
+const FILTER_PREFIX: &'static [u8] = b"cfilter:";
+
+/// derive the storage key for a block's filter. This stays a proper
+/// `HeaderHash` (rather than an arbitrary-length byte vector) so it fits
+/// whatever key type `storage::blob` expects, the same as the block's own
+/// blob does.
+fn filter_key(block_hash: &HeaderHash) -> HeaderHash {
+    let mut bytes = Vec::with_capacity(FILTER_PREFIX.len() + block_hash.as_ref().len());
+    bytes.extend_from_slice(FILTER_PREFIX);
+    bytes.extend_from_slice(block_hash.as_ref());
+    HeaderHash::new(&bytes)
+}
+
+/// Collect the output addresses of a block; genesis blocks carry none.
+fn output_addresses(blk: &Block) -> Vec<ExtendedAddr> {
+    let mut addresses = vec![];
+    if let Block::MainBlock(mblk) = blk {
+        for txaux in mblk.body.tx.iter() {
+            for txout in &txaux.tx.outputs {
+                addresses.push(txout.address.clone());
+            }
+        }
+    }
+    addresses
+}
+
+/// Read-only lookup: return the stored filter for a block, or `None` if it
+/// has not been built yet. Never writes to storage.
+pub fn load(storage: &Storage, block_hash: &HeaderHash) -> Option<CompactFilter> {
+    let bytes = blob::read(storage, &filter_key(block_hash)).ok()?;
+    CompactFilter::deserialize(&bytes)
+}
+
+/// Build the filter for a freshly-written block and persist it, keyed by the
+/// block's hash. No query path calls it: unlike a lookup, writing can fail
+/// (read-only storage, full disk, ...) so the error is returned rather than
+/// unwrapped.
+pub fn build_and_store(storage: &Storage, block_hash: &HeaderHash, blk: &Block) -> blob::Result<CompactFilter> {
+    let filter = CompactFilter::build(block_hash.as_ref(), &output_addresses(blk));
+    blob::write(storage, &filter_key(block_hash), &filter.serialize())?;
+    Ok(filter)
+}
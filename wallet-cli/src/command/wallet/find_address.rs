@@ -5,6 +5,7 @@ use clap::{ArgMatches, Arg, App};
 use config::{Config};
 use storage::{tag, pack};
 use blockchain::{Block};
+use super::filter_store;
 
 pub struct FindAddress;
 
@@ -34,11 +35,21 @@ impl HasCommand for FindAddress {
         while let Some(blk) = iter.next_block().unwrap() {
             let hdr = blk.get_header();
             let blk_hash = hdr.compute_hash();
-            match blk {
+            match &blk {
                 Block::GenesisBlock(_) => {
                     println!("    ignoring {} block", hdr.get_blockdate());
                 },
                 Block::MainBlock(mblk) => {
+                    // when a filter was already stored for this block (by
+                    // filter_store::build_and_store), decode it and skip the block entirely when
+                    // none of the watched addresses can match. Blocks without
+                    // a stored filter yet just fall through to the scan
+                    // below, same as before this feature existed.
+                    if let Some(filter) = filter_store::load(&storage, &blk_hash) {
+                        if !addresses.iter().any(|a| filter.contains(blk_hash.as_ref(), a)) {
+                            continue;
+                        }
+                    }
                     for txaux in mblk.body.tx.iter() {
                         for txout in &txaux.tx.outputs {
                             if let Some(_) = addresses.iter().find(|a| *a == &txout.address) {
@@ -0,0 +1,146 @@
+//! Watch-only balance scanner.
+//!
+//! Unlike `find-addresses`, which only reports blocks an address appears in,
+//! `scan-addresses` follows an address's outputs through to the inputs that
+//! later spend them. It keeps a live UTXO map keyed by `(TxId, output index)`
+//! for the watched `ExtendedAddr` set while iterating blocks: matching
+//! outputs are inserted, and any outpoint consumed by a later transaction's
+//! inputs is removed. At the end of the scan each watched address gets a
+//! total received, total spent, current balance and the list of outpoints
+//! still unspent.
+//!
+//! `--from-epoch` is a coverage bound, not a resumable checkpoint: no UTXO
+//! state is persisted between runs, so starting above epoch 0 means any
+//! output created (or spent) before that epoch is invisible to this scan,
+//! and the reported totals for addresses active earlier will be partial.
+
+use std::collections::BTreeMap;
+use wallet_crypto::{cbor, address::ExtendedAddr, tx::{TxId, Coin}};
+use wallet_crypto::util::base58;
+use command::{HasCommand};
+use clap::{ArgMatches, Arg, App};
+use config::{Config};
+use blockchain::{Block, BlockDate};
+
+pub struct ScanAddresses;
+
+/// an unspent output still owned by one of the watched addresses.
+struct Utxo {
+    address: ExtendedAddr,
+    value: Coin,
+    date: BlockDate,
+}
+
+/// running totals for a single watched address.
+#[derive(Default)]
+struct Balance {
+    received: u64,
+    spent: u64,
+}
+
+impl HasCommand for ScanAddresses {
+    type Output = ();
+    type Config = ();
+
+    const COMMAND : &'static str = "scan-addresses";
+
+    fn clap_options<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+        app.about("watch-only scan: track spends and report UTXO balances for the given addresses")
+            .arg(Arg::with_name("name").help("the network name").index(1).required(true))
+            .arg(Arg::with_name("addresses").help("list of addresses to watch").multiple(true).required(true).index(2))
+            .arg(Arg::with_name("from-epoch").long("from-epoch").takes_value(true).help("skip epochs before this one; NOT a resumable checkpoint, totals for addresses active earlier will be partial"))
+            .arg(Arg::with_name("json").long("json").help("emit machine-readable JSON instead of plain text"))
+    }
+
+    fn run(_: Self::Config, args: &ArgMatches) -> Self::Output {
+        let name = value_t!(args.value_of("name"), String).unwrap();
+        let mut config = Config::default();
+        config.network = name;
+        let storage = config.get_storage().unwrap();
+        let from_epoch = value_t!(args.value_of("from-epoch"), u32).unwrap_or(0);
+        let as_json = args.is_present("json");
+        if from_epoch > 0 {
+            eprintln!(
+                "warning: --from-epoch {} carries no UTXO state from earlier epochs; received/spent/balance will be partial for addresses active before this epoch",
+                from_epoch
+            );
+        }
+
+        let addresses_bytes : Vec<_> = values_t!(args.values_of("addresses"), String)
+            .unwrap().iter().map(|s| base58::decode(s).unwrap()).collect();
+        let mut addresses : Vec<ExtendedAddr> = vec![];
+        for address in addresses_bytes {
+            addresses.push(cbor::decode_from_cbor(&address).unwrap());
+        }
+
+        let mut utxos : BTreeMap<(TxId, u32), Utxo> = BTreeMap::new();
+        let mut balances : BTreeMap<ExtendedAddr, Balance> = addresses.iter().cloned().map(|a| (a, Balance::default())).collect();
+
+        let mut iter = storage.iterate_from_epoch(from_epoch).unwrap();
+        while let Some(blk) = iter.next_block().unwrap() {
+            let hdr = blk.get_header();
+            match &blk {
+                Block::GenesisBlock(_) => {},
+                Block::MainBlock(mblk) => {
+                    for txaux in mblk.body.tx.iter() {
+                        // spends: drop any watched outpoint this transaction consumes.
+                        for txin in &txaux.tx.inputs {
+                            if let Some(utxo) = utxos.remove(&(txin.id.clone(), txin.index)) {
+                                let balance = balances.get_mut(&utxo.address).unwrap();
+                                balance.spent += u64::from(utxo.value);
+                            }
+                        }
+                        // receives: track new outputs paying a watched address.
+                        let txid = txaux.tx.id();
+                        for (index, txout) in txaux.tx.outputs.iter().enumerate() {
+                            if let Some(balance) = balances.get_mut(&txout.address) {
+                                balance.received += u64::from(txout.value);
+                                utxos.insert((txid.clone(), index as u32), Utxo {
+                                    address: txout.address.clone(),
+                                    value: txout.value,
+                                    date: hdr.get_blockdate(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        report(&addresses, &balances, &utxos, as_json);
+    }
+}
+
+fn report(
+    addresses: &[ExtendedAddr],
+    balances: &BTreeMap<ExtendedAddr, Balance>,
+    utxos: &BTreeMap<(TxId, u32), Utxo>,
+    as_json: bool,
+) {
+    for address in addresses {
+        let balance = balances.get(address).unwrap();
+        let encoded = base58::encode(&cbor::encode_to_cbor(address).unwrap());
+        let unspent : Vec<_> = utxos.iter()
+            .filter(|(_, utxo)| &utxo.address == address)
+            .collect();
+        let confirmed = balance.received - balance.spent;
+
+        if as_json {
+            println!("{{\"address\":\"{}\",\"received\":{},\"spent\":{},\"balance\":{},\"unspent\":[{}]}}",
+                encoded,
+                balance.received,
+                balance.spent,
+                confirmed,
+                unspent.iter().map(|((txid, index), utxo)| format!(
+                    "{{\"txid\":\"{}\",\"index\":{},\"value\":{},\"date\":\"{}\"}}",
+                    txid, index, u64::from(utxo.value), utxo.date
+                )).collect::<Vec<_>>().join(","),
+            );
+        } else {
+            println!("{}: received {}, spent {}, balance {}", encoded, balance.received, balance.spent, confirmed);
+            for ((txid, index), utxo) in unspent {
+                println!("    unspent {}#{} worth {} at {}", txid, index, u64::from(utxo.value), utxo.date);
+            }
+        }
+    }
+}